@@ -1,13 +1,260 @@
-use std::{fs::File, io::{Write, Seek, SeekFrom}, path::Path, sync::{Arc, Mutex}, time::Instant};
+use std::{collections::HashSet, fs::File, io::{Read, Write, Seek, SeekFrom}, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}, time::{Duration, Instant}};
 use reqwest::{Client, Response, StatusCode};
-use tokio::{sync::Mutex as AsyncMutex, task};
+use tokio::{sync::{mpsc, Mutex as AsyncMutex, Semaphore}, task};
 use indicatif::{ProgressBar, ProgressStyle};
 use futures_util::StreamExt;
+use serde::{Serialize, Deserialize};
+use bytes::Bytes;
 use tracing::{info, error};
 
+/// Callback interface for download progress, decoupling `DownloadEngine` from any
+/// particular UI. `on_progress` reports cumulative bytes downloaded so far plus
+/// the current speed; implementations are expected to be cheap since it's called
+/// once per chunk received.
+pub trait DownloadObserver {
+    fn on_total(&self, total: u64);
+    fn on_progress(&self, downloaded: u64, speed_bps: f64);
+    fn on_complete(&self);
+    fn on_error(&self, e: &dyn std::error::Error);
+}
+
+/// Default observer that renders the download with an `indicatif` progress bar,
+/// matching the engine's previous built-in behavior. `ProgressBar` is already a
+/// cheap `Clone + Send + Sync` handle over shared internal state (the baseline
+/// code cloned it across chunk tasks directly), so no extra locking is needed here.
+pub struct IndicatifObserver {
+    bar: ProgressBar,
+}
+
+impl Default for IndicatifObserver {
+    fn default() -> Self {
+        let bar = ProgressBar::new(0);
+        bar.set_style(ProgressStyle::default_bar());
+        bar.set_message("Downloading");
+        Self { bar }
+    }
+}
+
+impl DownloadObserver for IndicatifObserver {
+    fn on_total(&self, total: u64) {
+        self.bar.set_length(total);
+    }
+
+    fn on_progress(&self, downloaded: u64, speed_bps: f64) {
+        self.bar.set_position(downloaded);
+        self.bar.set_message(format!("Speed: {}", DownloadEngine::format_speed(speed_bps)));
+    }
+
+    fn on_complete(&self) {
+        self.bar.finish_with_message("Download complete");
+    }
+
+    fn on_error(&self, e: &dyn std::error::Error) {
+        self.bar.finish_with_message(format!("Download failed: {}", e));
+    }
+}
+
+/// Which decompressor to wrap the tar stream in, chosen from the URL or
+/// `Content-Type` of the response.
+enum ArchiveFormat {
+    TarGz,
+    TarBz2,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    fn detect(url: &str, content_type: &str) -> Option<Self> {
+        let url = url.to_lowercase();
+        if url.ends_with(".tar.gz") || url.ends_with(".tgz") || content_type.contains("gzip") {
+            Some(Self::TarGz)
+        } else if url.ends_with(".tar.bz2") || url.ends_with(".tbz2") || content_type.contains("bzip2") {
+            Some(Self::TarBz2)
+        } else if url.ends_with(".tar.zst") || content_type.contains("zstd") {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+/// Adapts the async byte stream coming off the network into a blocking
+/// `std::io::Read`, so a synchronous decoder/tar unpacker can consume it as
+/// bytes arrive instead of waiting for the whole archive to land on disk.
+struct ChannelReader {
+    rx: mpsc::Receiver<std::io::Result<Bytes>>,
+    current: Bytes,
+}
+
+impl ChannelReader {
+    fn new(rx: mpsc::Receiver<std::io::Result<Bytes>>) -> Self {
+        Self { rx, current: Bytes::new() }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if !self.current.is_empty() {
+                let n = self.current.len().min(buf.len());
+                buf[..n].copy_from_slice(&self.current[..n]);
+                self.current = self.current.split_off(n);
+                return Ok(n);
+            }
+            match self.rx.blocking_recv() {
+                Some(Ok(chunk)) => self.current = chunk,
+                Some(Err(e)) => return Err(e),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Sidecar JSON tracking which byte ranges of a `.partial` file are complete,
+/// so an interrupted download can resume at chunk granularity instead of
+/// trusting the raw on-disk byte count.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProgressManifest {
+    url: String,
+    total_size: u64,
+    chunk_size: u64,
+    completed_ranges: Vec<(u64, u64)>,
+}
+
+impl ProgressManifest {
+    fn fresh(url: &str, total_size: u64, chunk_size: u64) -> Self {
+        Self {
+            url: url.to_string(),
+            total_size,
+            chunk_size,
+            completed_ranges: vec![],
+        }
+    }
+
+    /// Loads the manifest at `path` if it exists and matches `url`/`total_size`/
+    /// `chunk_size`; a mismatch on any of these means the completed ranges were
+    /// computed against a different grid or file, so the partial is stale.
+    fn load_if_matching(path: &str, url: &str, total_size: u64, chunk_size: u64) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        let manifest: Self = serde_json::from_slice(&data).ok()?;
+        if manifest.url == url && manifest.total_size == total_size && manifest.chunk_size == chunk_size {
+            Some(manifest)
+        } else {
+            None
+        }
+    }
+
+    fn save(&self, path: &str) -> std::io::Result<()> {
+        let data = serde_json::to_vec(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        std::fs::write(path, data)
+    }
+}
+
+/// Shared token-bucket used to cap aggregate throughput across all chunk tasks.
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    rate: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// `rate` is bytes/sec; the bucket can burst up to one second's worth of `rate`.
+    fn new(rate: u64) -> Self {
+        // A rate of 0 would make `try_take`'s `deficit / self.rate` divide by zero and
+        // hand `Duration::from_secs_f64` a non-finite value, which panics. Clamp to the
+        // smallest valid rate instead, which throttles effectively down to a crawl
+        // rather than crashing the chunk task.
+        let rate = (rate as f64).max(1.0);
+        Self {
+            capacity: rate,
+            tokens: rate,
+            rate,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + self.rate * elapsed).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Returns `Ok(())` if `amount` tokens were taken, or `Err(wait)` with how long to
+    /// sleep before the caller should retry.
+    fn try_take(&mut self, amount: f64) -> Result<(), Duration> {
+        self.refill();
+        // An `amount` above `capacity` could never be covered, so it is let through once
+        // the bucket is full; `tokens` then goes negative and later callers wait it off.
+        let needed = amount.min(self.capacity);
+        if self.tokens >= needed {
+            self.tokens -= amount;
+            Ok(())
+        } else {
+            let deficit = needed - self.tokens;
+            Err(Duration::from_secs_f64(deficit / self.rate))
+        }
+    }
+}
+
+/// Blocks until `amount` bytes' worth of tokens are available in `bucket`.
+async fn throttle(bucket: &Arc<Mutex<TokenBucket>>, amount: u64) {
+    loop {
+        let wait = {
+            let mut bucket = bucket.lock().unwrap();
+            match bucket.try_take(amount as f64) {
+                Ok(()) => return,
+                Err(wait) => wait,
+            }
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Result of probing a URL for its size and range support before committing to a
+/// parallel chunked download.
+struct ProbeResult {
+    total_size: u64,
+    supports_ranges: bool,
+}
+
+/// Carries the concrete cause of a chunk task giving up, so `download` can
+/// surface something more useful than "some chunks didn't finish".
+#[derive(Debug)]
+struct ChunkError {
+    start: u64,
+    end: u64,
+    message: String,
+}
+
+impl std::fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "chunk {}-{} failed: {}", self.start, self.end, self.message)
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+/// `base * 2^attempt`, capped at `max`, plus up to 30% random jitter so that
+/// many chunks backing off at once don't all retry in lockstep.
+fn backoff_delay(base: Duration, max: Duration, attempt: u32) -> Duration {
+    let exp = base.as_secs_f64() * 2f64.powi(attempt as i32);
+    let capped = exp.min(max.as_secs_f64());
+    let jitter = capped * rand::random::<f64>() * 0.3;
+    Duration::from_secs_f64(capped + jitter)
+}
+
 pub struct DownloadEngine {
     client: Client,
     chunk_size: u64,
+    max_speed: Option<u64>,
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_concurrent: usize,
+    preallocate: bool,
+    observer: Option<Arc<dyn DownloadObserver + Send + Sync>>,
 }
 
 impl DownloadEngine {
@@ -16,129 +263,442 @@ impl DownloadEngine {
         Self {
             client: Client::new(),
             chunk_size,
+            max_speed: None,
+            max_retries: 5,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+            max_concurrent: 8,
+            preallocate: true,
+            observer: Some(Arc::new(IndicatifObserver::default())),
         }
     }
 
+    /// Caps aggregate throughput across all chunk tasks at `max_speed` bytes/sec
+    /// (default: unlimited). Pass `None` to remove the cap.
+    pub fn with_max_speed(mut self, max_speed: Option<u64>) -> Self {
+        self.max_speed = max_speed;
+        self
+    }
+
+    /// Overrides the progress observer (default: a built-in `indicatif` bar).
+    /// Pass `None` to disable progress reporting entirely.
+    pub fn with_observer(mut self, observer: Option<Arc<dyn DownloadObserver + Send + Sync>>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Toggles up-front free-space checking and file preallocation (default on).
+    pub fn with_preallocate(mut self, preallocate: bool) -> Self {
+        self.preallocate = preallocate;
+        self
+    }
+
+    /// Overrides the per-chunk retry policy (defaults: 5 retries, 200ms base delay, 10s cap).
+    pub fn with_retry_policy(mut self, max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Caps how many chunk requests are in flight at once (default 8), so a small
+    /// `chunk_size` on a large file doesn't fire thousands of simultaneous requests.
+    /// Clamped to at least 1, since a 0-permit semaphore would block every chunk forever.
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self
+    }
+
     pub async fn download(&self, url: &str, target: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let total_size = self.get_content_length(url).await?;
-        if total_size == 0 {
-            error!("Failed to fetch content length, falling back to streaming");
+        let probe = self.probe(url).await?;
+        if !probe.supports_ranges {
+            info!("Server does not advertise ranged support, falling back to streaming");
             return self.download_fallback(url, target).await;
         }
+        let total_size = probe.total_size;
         info!("Total file size: {} bytes", total_size);
-        
-        let existing_size = Self::get_existing_file_size(target)?;
-        let progress = ProgressBar::new(total_size);
-        progress.set_style(ProgressStyle::default_bar());
-        progress.set_message("Downloading");
-        progress.set_position(existing_size);
-
-        let path = Path::new(target);
-        let file = Arc::new(AsyncMutex::new(File::options().create(true).append(true).open(path)?));
+
+        let partial_path = Self::partial_path(target);
+        let manifest_path = Self::manifest_path(target);
+
+        let manifest = match ProgressManifest::load_if_matching(&manifest_path, url, total_size, self.chunk_size) {
+            Some(manifest) => manifest,
+            None => {
+                // Either this is a fresh download or the manifest is missing, unreadable or
+                // stale (URL, size or chunk size changed); the old partial bytes can't be trusted.
+                let _ = std::fs::remove_file(&partial_path);
+                let _ = std::fs::remove_file(&manifest_path);
+                ProgressManifest::fresh(url, total_size, self.chunk_size)
+            }
+        };
+        let completed: HashSet<(u64, u64)> = manifest.completed_ranges.iter().copied().collect();
+        let already_downloaded: u64 = completed.iter().map(|(start, end)| end - start + 1).sum();
+
+        if let Some(observer) = &self.observer {
+            observer.on_total(total_size);
+            observer.on_progress(already_downloaded, 0.0);
+        }
+
+        let file = File::options().create(true).write(true).open(&partial_path)?;
+        if self.preallocate {
+            let existing_size = file.metadata()?.len();
+            let needed = total_size.saturating_sub(existing_size);
+            let free = Self::available_space(&partial_path)?;
+            if needed > free {
+                return Err(format!(
+                    "not enough free disk space: need {} more bytes, only {} available",
+                    needed, free
+                ).into());
+            }
+            Self::preallocate_file(&file, total_size)?;
+        }
+        let file = Arc::new(AsyncMutex::new(file));
+        let manifest = Arc::new(Mutex::new(manifest));
         let start_time = Instant::now();
-        let num_chunks = (total_size - existing_size + self.chunk_size - 1) / self.chunk_size;
+        let num_chunks = (total_size + self.chunk_size - 1) / self.chunk_size;
+        let bucket = self.max_speed.map(|speed| Arc::new(Mutex::new(TokenBucket::new(speed))));
+        let semaphore = Arc::new(Semaphore::new(self.max_concurrent));
+        let downloaded_total = Arc::new(AtomicU64::new(already_downloaded));
 
         let mut handles = vec![];
         for i in 0..num_chunks {
+            let start = i * self.chunk_size;
+            let end = (start + self.chunk_size - 1).min(total_size - 1);
+            if completed.contains(&(start, end)) {
+                continue;
+            }
+
             let client = self.client.clone();
             let file = file.clone();
-            let progress = progress.clone();
             let url = url.to_string();
-            let start = existing_size + i * self.chunk_size;
-            let end = (start + self.chunk_size - 1).min(total_size - 1);
-            
+            let bucket = bucket.clone();
+            let manifest = manifest.clone();
+            let manifest_path = manifest_path.clone();
+            let max_retries = self.max_retries;
+            let base_delay = self.base_delay;
+            let max_delay = self.max_delay;
+            let semaphore = semaphore.clone();
+            let downloaded_total = downloaded_total.clone();
+            let observer = self.observer.clone();
+
             let handle = task::spawn(async move {
-                info!("Downloading chunk: {} - {}", start, end);
-                let response = client.get(&url).header("Range", format!("bytes={}-{}", start, end)).send().await;
-                if let Err(e) = response {
-                    error!("Request failed for range {}-{}: {:?}", start, end, e);
-                    return None;
-                }
-                let response = response.unwrap();
-                if response.status() != StatusCode::PARTIAL_CONTENT && response.status() != StatusCode::OK {
-                    error!("Server does not support partial download. Status: {:?}", response.status());
-                    return None;
-                }
-                let mut stream = response.bytes_stream();
-                let mut downloaded = 0;
+                let chunk_err = |message: String| ChunkError { start, end, message };
+
+                let _permit = semaphore.acquire_owned().await
+                    .map_err(|e| chunk_err(format!("semaphore closed: {:?}", e)))?;
+                let mut downloaded: u64 = 0;
+                let mut attempt = 0u32;
 
-                while let Some(chunk) = stream.next().await {
-                    let chunk = match chunk {
-                        Ok(c) => c,
+                'retry: loop {
+                    let range_start = start + downloaded;
+                    info!("Downloading chunk: {} - {} (attempt {})", range_start, end, attempt + 1);
+                    let response = client.get(&url).header("Range", format!("bytes={}-{}", range_start, end)).send().await;
+                    let response = match response {
+                        Ok(resp) if resp.status() == StatusCode::PARTIAL_CONTENT || resp.status() == StatusCode::OK => resp,
+                        Ok(resp) => {
+                            let message = format!("server does not support partial download, status: {:?}", resp.status());
+                            error!("{}", message);
+                            return Err(chunk_err(message));
+                        }
                         Err(e) => {
-                            error!("Error while downloading chunk {}-{}: {:?}", start, end, e);
-                            return None;
+                            if attempt >= max_retries {
+                                let message = format!("request failed after {} attempts: {:?}", attempt + 1, e);
+                                error!("Range {}-{}: {}", range_start, end, message);
+                                return Err(chunk_err(message));
+                            }
+                            let delay = backoff_delay(base_delay, max_delay, attempt);
+                            error!("Request failed for range {}-{}: {:?}, retrying in {:?}", range_start, end, e, delay);
+                            attempt += 1;
+                            tokio::time::sleep(delay).await;
+                            continue 'retry;
                         }
                     };
-                    let mut file_lock = file.lock().await;
-                    file_lock.seek(SeekFrom::Start(start + downloaded)).ok()?;
-                    file_lock.write_all(&chunk).ok()?;
-                    downloaded += chunk.len() as u64;
-                    progress.inc(chunk.len() as u64);
-                    
-                    let elapsed = start_time.elapsed().as_secs_f64();
-                    let speed = if elapsed > 0.0 { Self::format_speed(progress.position() as f64 / elapsed) } else { "0 B/s".to_string() };
-                    progress.set_message(format!("Speed: {}", speed));
+
+                    let mut stream = response.bytes_stream();
+                    while let Some(chunk) = stream.next().await {
+                        let chunk = match chunk {
+                            Ok(c) => c,
+                            Err(e) => {
+                                if attempt >= max_retries {
+                                    let message = format!("stream error after {} attempts: {:?}", attempt + 1, e);
+                                    error!("Chunk {}-{}: {}", start, end, message);
+                                    return Err(chunk_err(message));
+                                }
+                                let delay = backoff_delay(base_delay, max_delay, attempt);
+                                error!("Error while downloading chunk {}-{}: {:?}, retrying in {:?}", start, end, e, delay);
+                                attempt += 1;
+                                tokio::time::sleep(delay).await;
+                                continue 'retry;
+                            }
+                        };
+                        if let Some(bucket) = &bucket {
+                            throttle(bucket, chunk.len() as u64).await;
+                        }
+                        let mut file_lock = file.lock().await;
+                        file_lock.seek(SeekFrom::Start(start + downloaded))
+                            .map_err(|e| chunk_err(format!("seek failed: {:?}", e)))?;
+                        file_lock.write_all(&chunk)
+                            .map_err(|e| chunk_err(format!("write failed: {:?}", e)))?;
+                        downloaded += chunk.len() as u64;
+                        let total_downloaded = downloaded_total.fetch_add(chunk.len() as u64, Ordering::Relaxed) + chunk.len() as u64;
+
+                        if let Some(observer) = &observer {
+                            let elapsed = start_time.elapsed().as_secs_f64();
+                            let speed = if elapsed > 0.0 { total_downloaded as f64 / elapsed } else { 0.0 };
+                            observer.on_progress(total_downloaded, speed);
+                        }
+                    }
+                    // A stream that ends cleanly but short of `end` is retried from where
+                    // it stopped instead of being recorded as a complete range.
+                    let expected = end - start + 1;
+                    if downloaded < expected {
+                        if attempt >= max_retries {
+                            let message = format!("stream ended after {} of {} bytes", downloaded, expected);
+                            error!("Chunk {}-{}: {}", start, end, message);
+                            return Err(chunk_err(message));
+                        }
+                        let delay = backoff_delay(base_delay, max_delay, attempt);
+                        attempt += 1;
+                        tokio::time::sleep(delay).await;
+                        continue 'retry;
+                    }
+                    break;
+                }
+
+                file.lock().await.flush()
+                    .map_err(|e| chunk_err(format!("flush failed: {:?}", e)))?;
+
+                // Only record the range as complete once every byte of it has landed
+                // on disk, so a mid-chunk crash doesn't mark it done.
+                let mut manifest = manifest.lock().unwrap();
+                manifest.completed_ranges.push((start, end));
+                if let Err(e) = manifest.save(&manifest_path) {
+                    error!("Failed to persist progress manifest: {:?}", e);
                 }
                 info!("Chunk {}-{} downloaded successfully", start, end);
-                Some(())
+                Ok(())
             });
             handles.push(handle);
         }
 
+        let mut first_error: Option<ChunkError> = None;
         for handle in handles {
-            handle.await.ok();
+            match handle.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    if first_error.is_none() {
+                        first_error = Some(e);
+                    }
+                }
+                Err(e) => {
+                    error!("Chunk task panicked or was cancelled: {:?}", e);
+                }
+            }
         }
 
-        progress.finish_with_message("Download complete");
-        info!("Download completed successfully!");
-        Ok(())
+        let finished = manifest.lock().unwrap().completed_ranges.len() as u64 == num_chunks;
+        if finished {
+            std::fs::rename(&partial_path, target)?;
+            let _ = std::fs::remove_file(&manifest_path);
+            if let Some(observer) = &self.observer {
+                observer.on_complete();
+            }
+            info!("Download completed successfully!");
+            Ok(())
+        } else {
+            let err: Box<dyn std::error::Error> = match first_error {
+                Some(e) => e.into(),
+                None => format!(
+                    "download left incomplete: some chunks did not finish, partial file kept at {}",
+                    partial_path
+                ).into(),
+            };
+            if let Some(observer) = &self.observer {
+                observer.on_error(err.as_ref());
+            }
+            error!("{}", err);
+            Err(err)
+        }
     }
 
-    async fn get_content_length(&self, url: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    /// Probes `url` for its size and whether the server supports byte-range requests,
+    /// so `download` can decide up front instead of discovering it mid-transfer.
+    async fn probe(&self, url: &str) -> Result<ProbeResult, Box<dyn std::error::Error>> {
         let response = self.client.head(url).send().await;
-        match response {
-            Ok(resp) if resp.status().is_success() => {
-                if let Some(length) = resp.content_length() {
-                    return Ok(length);
-                }
-            }
+        let resp = match response {
+            Ok(resp) if resp.status().is_success() => resp,
             _ => {
                 error!("HEAD request failed, trying GET request to determine file size");
-                let resp = self.client.get(url).send().await?;
-                if let Some(length) = resp.content_length() {
-                    return Ok(length);
-                }
+                self.client.get(url).send().await?
             }
-        }
-        Ok(0)
+        };
+
+        let total_size = resp.content_length().unwrap_or(0);
+        let accepts_ranges = resp
+            .headers()
+            .get(reqwest::header::ACCEPT_RANGES)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v != "none")
+            .unwrap_or(false);
+
+        Ok(ProbeResult {
+            total_size,
+            supports_ranges: accepts_ranges && total_size > 0,
+        })
     }
 
     async fn download_fallback(&self, url: &str, target: &str) -> Result<(), Box<dyn std::error::Error>> {
         let response = self.client.get(url).send().await?;
+        let total_size = response.content_length().unwrap_or(0);
         let mut file = File::create(target)?;
+        if self.preallocate && total_size > 0 {
+            let free = Self::available_space(target)?;
+            if total_size > free {
+                return Err(format!(
+                    "not enough free disk space: need {} bytes, only {} available",
+                    total_size, free
+                ).into());
+            }
+            Self::preallocate_file(&file, total_size)?;
+        }
         let mut stream = response.bytes_stream();
-        let progress = ProgressBar::new_spinner();
-        progress.set_message("Downloading");
-        
+        let start_time = Instant::now();
+        let mut downloaded: u64 = 0;
+        let bucket = self.max_speed.map(|speed| Arc::new(Mutex::new(TokenBucket::new(speed))));
+
+        if let Some(observer) = &self.observer {
+            observer.on_total(total_size);
+        }
+
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
+            let chunk = match chunk {
+                Ok(c) => c,
+                Err(e) => {
+                    let err: Box<dyn std::error::Error> = e.into();
+                    if let Some(observer) = &self.observer {
+                        observer.on_error(err.as_ref());
+                    }
+                    return Err(err);
+                }
+            };
+            if let Some(bucket) = &bucket {
+                throttle(bucket, chunk.len() as u64).await;
+            }
             file.write_all(&chunk)?;
-            progress.inc(chunk.len() as u64);
+            downloaded += chunk.len() as u64;
+
+            if let Some(observer) = &self.observer {
+                let elapsed = start_time.elapsed().as_secs_f64();
+                let speed = if elapsed > 0.0 { downloaded as f64 / elapsed } else { 0.0 };
+                observer.on_progress(downloaded, speed);
+            }
+        }
+
+        if let Some(observer) = &self.observer {
+            observer.on_complete();
         }
-        progress.finish_with_message("Download complete");
         info!("Download completed successfully!");
         Ok(())
     }
-    
-    fn get_existing_file_size(target: &str) -> std::io::Result<u64> {
-        let path = Path::new(target);
-        if path.exists() {
-            let metadata = std::fs::metadata(path)?;
-            Ok(metadata.len())
-        } else {
-            Ok(0)
+
+    /// Downloads `url` and unpacks it into `target_dir` as the bytes arrive, instead
+    /// of writing the archive to disk first. Supports `.tar.gz`/`.tgz`, `.tar.bz2`/
+    /// `.tbz2`, and `.tar.zst`, detected from the URL or `Content-Type`. Extraction is
+    /// inherently sequential, so this always streams over a single connection rather
+    /// than going through the chunked/parallel path.
+    pub async fn download_and_extract(&self, url: &str, target_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(target_dir)?;
+
+        let response = self.client.get(url).send().await?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let format = ArchiveFormat::detect(url, &content_type)
+            .ok_or("could not determine archive format from URL or Content-Type")?;
+
+        // Bounded so the network side can't outrun the decoder/unpacker by more
+        // than a few chunks' worth of memory.
+        let (tx, rx) = mpsc::channel::<std::io::Result<Bytes>>(16);
+        let target_dir_owned = target_dir.to_string();
+        let extract_handle = task::spawn_blocking(move || {
+            Self::extract(format, ChannelReader::new(rx), &target_dir_owned)
+        });
+
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
+            if tx.send(chunk).await.is_err() {
+                break;
+            }
         }
+        drop(tx);
+
+        extract_handle.await?.map_err(|e| e as Box<dyn std::error::Error>)?;
+        info!("Download and extraction completed successfully!");
+        Ok(())
+    }
+
+    /// Drives the decompressor and `tar::Archive` over `reader`, unpacking entries
+    /// into `target_dir` as they become available. Runs on a blocking thread since
+    /// both the decoders and `tar::Archive::unpack` are synchronous.
+    fn extract(
+        format: ArchiveFormat,
+        reader: ChannelReader,
+        target_dir: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match format {
+            ArchiveFormat::TarGz => {
+                let decoder = flate2::read::GzDecoder::new(reader);
+                tar::Archive::new(decoder).unpack(target_dir)?;
+            }
+            ArchiveFormat::TarBz2 => {
+                let decoder = bzip2::read::BzDecoder::new(reader);
+                tar::Archive::new(decoder).unpack(target_dir)?;
+            }
+            ArchiveFormat::TarZst => {
+                let decoder = zstd::stream::read::Decoder::new(reader)?;
+                tar::Archive::new(decoder).unpack(target_dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Bytes free on the filesystem holding `path`, via `statvfs`.
+    fn available_space(path: &str) -> std::io::Result<u64> {
+        let dir = std::path::Path::new(path)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| std::path::Path::new("."));
+        let stat = nix::sys::statvfs::statvfs(dir)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        Ok(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+    }
+
+    /// Reserves `total_size` bytes for `file` up front so chunk writes land in
+    /// already-allocated extents. Uses `fallocate` on Linux, `set_len` elsewhere.
+    #[cfg(target_os = "linux")]
+    fn preallocate_file(file: &File, total_size: u64) -> std::io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+        match nix::fcntl::fallocate(file.as_raw_fd(), nix::fcntl::FallocateFlags::empty(), 0, total_size as i64) {
+            Ok(()) => Ok(()),
+            Err(_) => file.set_len(total_size),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn preallocate_file(file: &File, total_size: u64) -> std::io::Result<()> {
+        file.set_len(total_size)
+    }
+
+    fn partial_path(target: &str) -> String {
+        format!("{}.partial", target)
+    }
+
+    fn manifest_path(target: &str) -> String {
+        format!("{}.progress", target)
     }
 
     fn format_speed(speed: f64) -> String {
@@ -14,12 +14,16 @@ pub struct Args {
 
     #[clap(short, long)]
     pub target: String,
+
+    /// Cap aggregate download speed in bytes/sec (e.g. 1048576 for 1 MiB/s)
+    #[clap(long)]
+    pub max_speed: Option<u64>,
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
-    let engine = DownloadEngine::new(1048576 * 100);
+    let engine = DownloadEngine::new(1048576 * 100).with_max_speed(args.max_speed);
 
     match engine.download(&args.url, &args.target).await {
         Ok(_) => println!("Download completed successfully!"),